@@ -7,16 +7,23 @@ pub struct Ray {
     origin: Vector3<f32>,
     // Must be normalized.
     direction: Vector3<f32>,
+    // When during the camera's shutter interval this ray was emitted. Used to
+    // resolve the position of moving objects like `MovingSphere`.
+    time: f32,
 }
 
 // Coordinate system is: x is right, y is up, z is *towards* the viewer.
 
 impl Ray {
     pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Self::new_at_time(origin, direction, 0.0)
+    }
+    pub fn new_at_time(origin: Vector3<f32>, direction: Vector3<f32>, time: f32) -> Self {
         debug_assert!(direction.magnitude() != 0.0);
         Ray {
             origin,
             direction: direction.normalize(),
+            time,
         }
     }
     pub fn origin(&self) -> &Vector3<f32> {
@@ -25,6 +32,9 @@ impl Ray {
     pub fn direction(&self) -> &Vector3<f32> {
         &self.direction
     }
+    pub fn time(&self) -> f32 {
+        self.time
+    }
     pub fn at(&self, t: f32) -> Vector3<f32> {
         self.origin + t * self.direction
     }
@@ -41,16 +51,104 @@ pub struct HitRecord<'a> {
     pub material: &'a MaterialEnum,
 }
 
+// An axis-aligned bounding box, used to quickly reject rays that can't
+// possibly hit a `Hittable` before doing the real intersection test.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    // The slab method: test the ray against each axis-aligned pair of planes
+    // in turn, narrowing [t_min, t_max] down to the interval during which the
+    // ray is inside all three slabs.
+    pub fn hit(&self, ray: &Ray, mut t_min: f32, mut t_max: f32) -> bool {
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction()[axis];
+            let mut t0 = (self.min[axis] - ray.origin()[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin()[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+
+    // The smallest box containing both `a` and `b`.
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Aabb {
+        Aabb {
+            min: Vector3::new(
+                a.min.x.min(b.min.x),
+                a.min.y.min(b.min.y),
+                a.min.z.min(b.min.z),
+            ),
+            max: Vector3::new(
+                a.max.x.max(b.max.x),
+                a.max.y.max(b.max.y),
+                a.max.z.max(b.max.z),
+            ),
+        }
+    }
+}
+
 #[enum_dispatch]
 pub trait Hittable: std::fmt::Debug + Send + Sync {
     fn hits(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+
+    // The bounding box of this object over the time interval [t0, t1], if it
+    // has one. Returns None for objects with no finite box: an empty
+    // `HittableList`, a list holding such an object, or e.g. an infinite plane.
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb>;
 }
 
 #[enum_dispatch(Hittable)]
 #[derive(Debug)]
 pub enum HittableEnum {
     Sphere,
+    MovingSphere,
     HittableList,
+    Bvh,
+}
+
+// Shared quadratic intersection test, parameterized on the sphere's center so
+// that both `Sphere` (a fixed center) and `MovingSphere` (a center that's
+// interpolated from the ray's time) can use it.
+fn sphere_hits<'a>(
+    center: Vector3<f32>,
+    radius: f32,
+    material: &'a MaterialEnum,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<HitRecord<'a>> {
+    // t^2 + 2 * t(axis * direction) * t + axis * axis = radius^2; solve for t.
+    let axis = ray.origin() - center;
+    let b = 2.0 * axis.dot(ray.direction());
+    let c = axis.dot(&axis) - radius * radius;
+    let discriminant = b * b - 4.0 * c;
+    if discriminant >= 0.0 {
+        // Return the first intersection in the relevant range.
+        for sign in [-1.0, 1.0].into_iter() {
+            let t = (-b + discriminant.sqrt() * sign) / 2.0;
+            if t_min <= t && t < t_max {
+                let pos = ray.at(t);
+                let normal = (pos - center) / radius;
+                return Some(HitRecord {
+                    t,
+                    pos,
+                    normal: Some(normal),
+                    material,
+                });
+            }
+        }
+    }
+    None
 }
 
 #[derive(Debug)]
@@ -62,28 +160,61 @@ pub struct Sphere {
 
 impl Hittable for Sphere {
     fn hits(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        // t^2 + 2 * t(axis * direction) * t + axis * axis = radius^2; solve for t.
-        let axis = ray.origin() - self.center;
-        let b = 2.0 * axis.dot(ray.direction());
-        let c = axis.dot(&axis) - self.radius * self.radius;
-        let discriminant = b * b - 4.0 * c;
-        if discriminant >= 0.0 {
-            // Return the first intersection in the relevant range.
-            for sign in [-1.0, 1.0].into_iter() {
-                let t = (-b + discriminant.sqrt() * sign) / 2.0;
-                if t_min <= t && t < t_max {
-                    let pos = ray.at(t);
-                    let normal = (pos - self.center) / self.radius;
-                    return Some(HitRecord {
-                        t,
-                        pos,
-                        normal: Some(normal),
-                        material: &self.material,
-                    });
-                }
-            }
-        }
-        None
+        sphere_hits(self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        let radius = Vector3::new(self.radius, self.radius, self.radius);
+        Some(Aabb {
+            min: self.center - radius,
+            max: self.center + radius,
+        })
+    }
+}
+
+// A sphere whose center travels linearly from `center0` at `t0` to `center1`
+// at `t1`, used to render motion blur: each sampled ray carries a time within
+// the camera's shutter interval, and the sphere is hit-tested against its
+// position at that time.
+#[derive(Debug)]
+pub struct MovingSphere {
+    pub center0: Vector3<f32>,
+    pub center1: Vector3<f32>,
+    pub t0: f32,
+    pub t1: f32,
+    pub radius: f32,
+    pub material: MaterialEnum,
+}
+
+impl MovingSphere {
+    fn center(&self, time: f32) -> Vector3<f32> {
+        self.center0 + ((time - self.t0) / (self.t1 - self.t0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hits(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        sphere_hits(
+            self.center(ray.time()),
+            self.radius,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        let radius = Vector3::new(self.radius, self.radius, self.radius);
+        let box0 = Aabb {
+            min: self.center(t0) - radius,
+            max: self.center(t0) + radius,
+        };
+        let box1 = Aabb {
+            min: self.center(t1) - radius,
+            max: self.center(t1) + radius,
+        };
+        Some(Aabb::surrounding(&box0, &box1))
     }
 }
 
@@ -111,6 +242,82 @@ impl Hittable for HittableList {
         }
         best_rec
     }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        let mut result: Option<Aabb> = None;
+        for hittable in &self.hittables {
+            let bbox = hittable.bounding_box(t0, t1)?;
+            result = Some(match result {
+                Some(acc) => Aabb::surrounding(&acc, &bbox),
+                None => bbox,
+            });
+        }
+        result
+    }
+}
+
+// A node in a bounding-volume hierarchy: a binary tree where each node
+// stores the `Aabb` enclosing both of its children, so `hits` can skip
+// entire subtrees the ray can't possibly touch. Built by `Bvh::build`, which
+// recursively splits a flat list of hittables along a random axis.
+#[derive(Debug)]
+pub struct Bvh {
+    left: Box<HittableEnum>,
+    right: Box<HittableEnum>,
+    bbox: Aabb,
+}
+
+impl Bvh {
+    pub fn build<R: rand::Rng + ?Sized>(
+        mut hittables: Vec<HittableEnum>,
+        t0: f32,
+        t1: f32,
+        rng: &mut R,
+    ) -> HittableEnum {
+        assert!(!hittables.is_empty(), "can't build a BVH with no objects");
+        if hittables.len() == 1 {
+            return hittables.pop().unwrap();
+        }
+        let axis = rng.gen_range(0..3);
+        let min_on_axis = |h: &HittableEnum| {
+            h.bounding_box(t0, t1)
+                .expect("unbounded hittable in BVH")
+                .min[axis]
+        };
+        hittables.sort_by(|a, b| min_on_axis(a).partial_cmp(&min_on_axis(b)).unwrap());
+        let right_half = hittables.split_off(hittables.len() / 2);
+        let left = Bvh::build(hittables, t0, t1, rng);
+        let right = Bvh::build(right_half, t0, t1, rng);
+        let bbox = Aabb::surrounding(
+            &left.bounding_box(t0, t1).expect("unbounded hittable in BVH"),
+            &right.bounding_box(t0, t1).expect("unbounded hittable in BVH"),
+        );
+        Bvh {
+            left: Box::new(left),
+            right: Box::new(right),
+            bbox,
+        }
+        .into()
+    }
+}
+
+impl Hittable for Bvh {
+    fn hits(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
+        }
+        match self.left.hits(ray, t_min, t_max) {
+            Some(left_rec) => {
+                let right_rec = self.right.hits(ray, t_min, left_rec.t);
+                Some(right_rec.unwrap_or(left_rec))
+            }
+            None => self.right.hits(ray, t_min, t_max),
+        }
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(self.bbox)
+    }
 }
 
 // The camera. Used to compute direction of rays and so on.
@@ -122,9 +329,15 @@ pub struct Camera {
     u: Vector3<f32>,
     v: Vector3<f32>,
     lens_radius: f32,
+    // The shutter is open for the interval [t_open, t_close]; rays are
+    // stamped with a time sampled uniformly from it so that moving objects
+    // (see `MovingSphere`) render with motion blur.
+    t_open: f32,
+    t_close: f32,
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         origin: Vector3<f32>,
         look_at: Vector3<f32>,
@@ -133,6 +346,8 @@ impl Camera {
         aspect_ratio: f32,
         aperture: f32,
         focus_distance: f32,
+        t_open: f32,
+        t_close: f32,
     ) -> Self {
         let half_height = (vertical_fov / 2.0).tan();
         let half_width = aspect_ratio * half_height;
@@ -148,6 +363,8 @@ impl Camera {
             u,
             v,
             lens_radius: aperture / 2.0,
+            t_open,
+            t_close,
         }
     }
 
@@ -158,9 +375,15 @@ impl Camera {
         let lens_position: [f32; 2] = rng.sample(rand_distr::UnitDisc);
         let lens_offset =
             self.lens_radius * (lens_position[0] * self.u + lens_position[1] * self.v);
-        Ray::new(
+        let time = if self.t_open < self.t_close {
+            rng.gen_range(self.t_open..self.t_close)
+        } else {
+            self.t_open
+        };
+        Ray::new_at_time(
             self.origin + lens_offset,
             self.lower_left + s * self.horizontal + t * self.vertical - self.origin - lens_offset,
+            time,
         )
     }
 }
@@ -6,8 +6,10 @@ use minifb::{Window, WindowOptions};
 use nalgebra::Vector3;
 use palette::{LinSrgb, Mix, Srgb};
 use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
 use rayon::prelude::*;
 use std::error::Error;
+use std::hash::{Hash, Hasher};
 use std::sync::{Arc, Mutex};
 
 use geometry::*;
@@ -21,30 +23,41 @@ const SAMPLE_COUNT: usize = 50;
 // black.
 const BOUNCES: usize = 50;
 
+// `background` is the sky color straight up: a ray that escapes the scene
+// without hitting anything sees a gradient from white (straight down) to it.
+// Passing None renders pure black, for scenes lit only by `DiffuseLight`
+// emitters.
 pub fn color<T: Hittable, R: rand::Rng>(
     ray: &Ray,
     world: &T,
+    background: Option<LinSrgb>,
     bounce: usize,
     rng: &mut R,
 ) -> LinSrgb {
     if let Some(hit_record) = world.hits(ray, 0.001, std::f32::INFINITY) {
+        let emitted = hit_record.material.emitted();
         if bounce < BOUNCES {
             if let Some(scattering) = hit_record.material.scatter(ray, &hit_record, rng) {
-                return scattering.attenuation
-                    * color(&scattering.scattered, world, bounce + 1, rng);
+                return emitted
+                    + scattering.attenuation
+                        * color(&scattering.scattered, world, background, bounce + 1, rng);
             }
         }
-        return LinSrgb::new(0.0, 0.0, 0.0);
+        emitted
     } else {
-        let t = (ray.direction().y + 1.0) / 2.0;
-        let white = LinSrgb::new(1.0, 1.0, 1.0);
-        let blue = LinSrgb::new(0.5, 0.7, 1.0);
-        white.mix(&blue, t as f32)
+        match background {
+            Some(background) => {
+                let t = (ray.direction().y + 1.0) / 2.0;
+                let white = LinSrgb::new(1.0, 1.0, 1.0);
+                white.mix(&background, t as f32)
+            }
+            None => LinSrgb::new(0.0, 0.0, 0.0),
+        }
     }
 }
 
-fn construct_scene<R: rand::Rng>(rng: &mut R) -> HittableList {
-    let spheres = iproduct!(-11..11, -11..11).filter_map(|(x, z)| -> Option<Box<dyn Hittable>> {
+fn construct_scene<R: rand::Rng>(rng: &mut R) -> HittableEnum {
+    let spheres = iproduct!(-11..11, -11..11).filter_map(|(x, z)| -> Option<HittableEnum> {
         let center = Vector3::<f32>::new(
             (x as f32) + rng.gen::<f32>() * 0.9,
             0.2,
@@ -52,79 +65,118 @@ fn construct_scene<R: rand::Rng>(rng: &mut R) -> HittableList {
         );
         if (center - Vector3::new(4.0, 0.2, 0.0)).norm() > 0.9 {
             let material_choice: f32 = rng.gen();
-            let material: Box<dyn Material> = if material_choice < 0.8 {
-                // Diffuse.
-                Box::new(Lambertian {
-                    albedo: LinSrgb::new(
-                        rng.gen::<f32>() * rng.gen::<f32>(),
-                        rng.gen::<f32>() * rng.gen::<f32>(),
-                        rng.gen::<f32>() * rng.gen::<f32>(),
-                    ),
-                })
+            let material: MaterialEnum = if material_choice < 0.8 {
+                // Diffuse. Give it a bit of bounce during the exposure so the
+                // scene picks up some motion blur.
+                let albedo = LinSrgb::new(
+                    rng.gen::<f32>() * rng.gen::<f32>(),
+                    rng.gen::<f32>() * rng.gen::<f32>(),
+                    rng.gen::<f32>() * rng.gen::<f32>(),
+                );
+                let center1 = center + Vector3::new(0.0, rng.gen::<f32>() * 0.5, 0.0);
+                return Some(
+                    MovingSphere {
+                        center0: center,
+                        center1,
+                        t0: 0.0,
+                        t1: 1.0,
+                        radius: 0.2,
+                        material: Lambertian { albedo }.into(),
+                    }
+                    .into(),
+                );
             } else if material_choice < 0.95 {
                 // Metal.
-                Box::new(Metal {
+                Metal {
                     albedo: LinSrgb::new(
                         0.5 + rng.gen::<f32>() / 2.0,
                         0.5 + rng.gen::<f32>() / 2.0,
                         0.5 + rng.gen::<f32>() / 2.0,
                     ),
                     fuzz: rng.gen::<f32>() / 2.0,
-                })
+                }
+                .into()
             } else {
-                Box::new(Dielectric { index: 1.5 })
+                Dielectric { index: 1.5 }.into()
             };
-            Some(Box::new(Sphere {
-                center,
-                radius: 0.2,
-                material,
-            }))
+            Some(
+                Sphere {
+                    center,
+                    radius: 0.2,
+                    material,
+                }
+                .into(),
+            )
         } else {
             None
         }
     });
-    let others: Vec<Sphere> = vec![
+    let others: Vec<HittableEnum> = vec![
         Sphere {
             center: Vector3::new(0.0, -1000.0, 0.0),
             radius: 1000.0,
-            material: Box::new(Lambertian {
+            material: Lambertian {
                 albedo: LinSrgb::new(0.5, 0.5, 0.5),
-            }),
-        },
+            }
+            .into(),
+        }
+        .into(),
         Sphere {
             center: Vector3::new(0.0, 1.0, 0.0),
             radius: 1.0,
-            material: Box::new(Dielectric { index: 1.5 }),
-        },
+            material: Dielectric { index: 1.5 }.into(),
+        }
+        .into(),
         Sphere {
             center: Vector3::new(-4.0, 1.0, 0.0),
             radius: 1.0,
-            material: Box::new(Lambertian {
+            material: Lambertian {
                 albedo: LinSrgb::new(0.4, 0.2, 0.1),
-            }),
-        },
+            }
+            .into(),
+        }
+        .into(),
         Sphere {
             center: Vector3::new(4.0, 1.0, 0.0),
             radius: 1.0,
-            material: Box::new(Metal {
+            material: Metal {
                 albedo: LinSrgb::new(0.7, 0.6, 0.5),
                 fuzz: 0.0,
-            }),
-        },
+            }
+            .into(),
+        }
+        .into(),
     ];
-    HittableList {
-        hittables: spheres
-            .chain(
-                others
-                    .into_iter()
-                    .map(|s| -> Box<dyn Hittable> { Box::new(s) }),
-            )
-            .collect(),
-    }
+    Bvh::build(spheres.chain(others).collect(), 0.0, 1.0, rng)
 }
 
-pub fn render_into(buf: &mut [u32]) {
-    let scene = construct_scene(&mut rand::thread_rng());
+// Derives a stable per-pixel seed from the render seed and the pixel's
+// coordinates, so that a given pixel always produces the same sample
+// sequence regardless of thread scheduling or tiling.
+fn pixel_seed(seed: u64, row: usize, col: usize) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    seed.hash(&mut hasher);
+    row.hash(&mut hasher);
+    col.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Deterministic variant of `render_into`: rendering the same scene with the
+/// same `seed` always produces the same buffer, regardless of thread
+/// scheduling. This is what makes golden-image tests and A/B comparisons
+/// between materials possible.
+pub fn render_into_seeded(buf: &mut [u32], seed: u64) {
+    render_into_seeded_with_background(buf, seed, Some(LinSrgb::new(0.5, 0.7, 1.0)));
+}
+
+/// As `render_into_seeded`, but with the sky color exposed so scenes that
+/// are lit only by `DiffuseLight` emitters can pass `None` for a black sky.
+pub fn render_into_seeded_with_background(
+    buf: &mut [u32],
+    seed: u64,
+    background: Option<LinSrgb>,
+) {
+    let scene = construct_scene(&mut Pcg64Mcg::seed_from_u64(seed));
 
     let camera = Camera::new(
         Vector3::new(16.0, 2.0, 4.0),
@@ -132,6 +184,10 @@ pub fn render_into(buf: &mut [u32]) {
         Vector3::new(0.0, 1.0, 0.0),
         15.0f32.to_radians(),
         (WIDTH as f32) / (HEIGHT as f32),
+        0.1,
+        10.0,
+        0.0,
+        1.0,
     );
 
     // Since no worker thread will ever write to the same part of the buffer as
@@ -143,16 +199,16 @@ pub fn render_into(buf: &mut [u32]) {
     (0..HEIGHT)
         .into_par_iter()
         .for_each_with(buf_mutex, |buf_mutex, row| {
-            let mut rng = rand::thread_rng();
             let mut temp = vec![0u32; WIDTH];
             for col in 0..WIDTH {
+                let mut rng = Pcg64Mcg::seed_from_u64(pixel_seed(seed, row, col));
                 // Sample SAMPLE_COUNT times per pixel, then average them.
                 let color: palette::LinSrgb = (0..SAMPLE_COUNT)
                     .map(|_| {
                         let u = (col as f32 + rng.gen::<f32>()) / (WIDTH as f32);
                         let v = ((HEIGHT - 1 - row) as f32 + rng.gen::<f32>()) / (HEIGHT as f32);
-                        let ray = camera.ray(u, v);
-                        color(&ray, &scene, 0, &mut rng)
+                        let ray = camera.ray(u, v, &mut rng);
+                        color(&ray, &scene, background, 0, &mut rng)
                     })
                     .fold(LinSrgb::new(0.0, 0.0, 0.0), |a, b| a + b)
                     / (SAMPLE_COUNT as f32);
@@ -166,6 +222,12 @@ pub fn render_into(buf: &mut [u32]) {
         });
 }
 
+/// Convenience wrapper around `render_into_seeded` for when reproducibility
+/// doesn't matter.
+pub fn render_into(buf: &mut [u32]) {
+    render_into_seeded(buf, rand::thread_rng().gen());
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let mut window = Window::new("lufasu", WIDTH, HEIGHT, WindowOptions::default())?;
 
@@ -22,6 +22,13 @@ pub trait Material: std::fmt::Debug + Send + Sync {
         hit_record: &HitRecord,
         rng: &mut dyn rand::RngCore,
     ) -> Option<Scattering>;
+
+    /// Light this material emits on its own, independent of any ray
+    /// scattering off of it. Defaults to black; only light sources like
+    /// `DiffuseLight` need to override it.
+    fn emitted(&self) -> LinSrgb {
+        LinSrgb::new(0.0, 0.0, 0.0)
+    }
 }
 
 #[enum_dispatch(Material)]
@@ -30,6 +37,7 @@ pub enum MaterialEnum {
     Lambertian,
     Dielectric,
     Metal,
+    DiffuseLight,
 }
 
 #[derive(Debug)]
@@ -40,7 +48,7 @@ pub struct Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _ray: &Ray,
+        ray: &Ray,
         hit_record: &HitRecord,
         rng: &mut dyn rand::RngCore,
     ) -> Option<Scattering> {
@@ -48,7 +56,7 @@ impl Material for Lambertian {
             hit_record.normal.unwrap() + Vector3::<f32>::from(rng.sample(rand_distr::UnitSphere));
         Some(Scattering {
             attenuation: self.albedo,
-            scattered: Ray::new(hit_record.pos, direction),
+            scattered: Ray::new_at_time(hit_record.pos, direction, ray.time()),
         })
     }
 }
@@ -69,7 +77,7 @@ impl Material for Metal {
         let normal = hit_record.normal.unwrap();
         let reflected = reflect(ray.direction(), &normal)
             + Vector3::<f32>::from(rng.sample(rand_distr::UnitSphere)) * self.fuzz;
-        let scattered = Ray::new(hit_record.pos, reflected);
+        let scattered = Ray::new_at_time(hit_record.pos, reflected, ray.time());
         if scattered.direction().dot(&normal) > 0.0 {
             Some(Scattering {
                 attenuation: self.albedo,
@@ -123,11 +131,33 @@ impl Material for Dielectric {
             };
         Some(Scattering {
             attenuation,
-            scattered: Ray::new(hit_record.pos, direction),
+            scattered: Ray::new_at_time(hit_record.pos, direction, ray.time()),
         })
     }
 }
 
+// A material that emits light instead of scattering it, used as a light
+// source for scenes without a sky (enclosed rooms, night scenes, etc.).
+#[derive(Debug)]
+pub struct DiffuseLight {
+    pub emit: LinSrgb,
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _hit_record: &HitRecord,
+        _rng: &mut dyn rand::RngCore,
+    ) -> Option<Scattering> {
+        None
+    }
+
+    fn emitted(&self) -> LinSrgb {
+        self.emit
+    }
+}
+
 fn reflect(v: &Vector3<f32>, normal: &Vector3<f32>) -> Vector3<f32> {
     v - 2.0 * v.dot(normal) * normal
 }